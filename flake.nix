@@ -13,5 +13,10 @@
       perSystem = {pkgs, ...}: {
         formatter = pkgs.alejandra;
       };
+
+      flake.templates.default = {
+        path = ./templates/default;
+        description = "A flake building option documentation for a module with ndg";
+      };
     };
 }
@@ -0,0 +1,50 @@
+# Renders the html snippet loading the analytics provider configured through
+# the builder's `analytics` argument.
+{lib}: {
+  provider,
+  respectDoNotTrack ? true,
+  ...
+} @ analytics: let
+  inherit (builtins) toJSON;
+  inherit (lib.strings) optionalString;
+
+  # the script to load for each provider, and the setup it needs beforehand
+  providers = {
+    plausible = {
+      src = analytics.scriptUrl or "https://plausible.io/js/script.js";
+      setup = "script.dataset.domain = ${toJSON analytics.domain};";
+    };
+
+    goatcounter = {
+      src = "https://gc.zgo.at/count.js";
+      setup = "script.dataset.goatcounter = ${toJSON "https://${analytics.code}.goatcounter.com/count"};";
+    };
+
+    matomo = let
+      url = lib.removeSuffix "/" analytics.url;
+    in {
+      src = "${url}/matomo.js";
+      setup = ''
+        var _paq = (window._paq = window._paq || []);
+        _paq.push(["trackPageView"]);
+        _paq.push(["enableLinkTracking"]);
+        _paq.push(["setTrackerUrl", ${toJSON "${url}/matomo.php"}]);
+        _paq.push(["setSiteId", ${toJSON (toString analytics.siteId)}]);
+      '';
+    };
+  };
+
+  preset = providers.${provider};
+in
+  assert lib.assertOneOf "analytics.provider" provider (lib.attrNames providers); ''
+    <script>
+      (function () {
+        ${optionalString respectDoNotTrack ''if (navigator.doNotTrack === "1" || window.doNotTrack === "1") return;''}
+        var script = document.createElement("script");
+        script.async = true;
+        script.src = ${toJSON preset.src};
+        ${preset.setup}
+        document.head.appendChild(script);
+      })();
+    </script>
+  ''
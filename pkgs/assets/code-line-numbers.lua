@@ -0,0 +1,5 @@
+-- Numbers the lines of every code block, using pandoc's numberLines class.
+function CodeBlock(block)
+  block.classes:insert("numberLines")
+  return block
+end
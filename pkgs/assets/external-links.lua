@@ -0,0 +1,32 @@
+-- Marks links leaving the documentation with the `external` class when the
+-- `external-links-icon` metadata is set, and makes them open in a new tab when
+-- the `external-links-new-tab` metadata is set.
+local icon = false
+local new_tab = false
+
+local function read_meta(meta)
+  icon = meta["external-links-icon"] == true
+  new_tab = meta["external-links-new-tab"] == true
+end
+
+local function mark_external(link)
+  if not link.target:match("^https?://") then
+    return nil
+  end
+
+  if icon then
+    link.classes:insert("external")
+  end
+  if new_tab then
+    link.attributes.target = "_blank"
+    link.attributes.rel = "noopener noreferrer"
+  end
+  return link
+end
+
+-- metadata has to be read in a separate pass, as pandoc would otherwise
+-- traverse the inlines before the metadata
+return {
+  { Meta = read_meta },
+  { Link = mark_external },
+}
@@ -0,0 +1,40 @@
+-- Adds a permalink anchor to every heading with an id, placed according to the
+-- `anchor-position` metadata. The link itself is empty, as pandoc would
+-- otherwise copy the symbol into the table of contents; the stylesheet shows
+-- the `anchor-symbol` metadata, carried in its data-anchor-symbol attribute.
+local symbol = "#"
+local position = "after"
+
+local function read_meta(meta)
+  if meta["anchor-symbol"] then
+    symbol = pandoc.utils.stringify(meta["anchor-symbol"])
+  end
+  if meta["anchor-position"] then
+    position = pandoc.utils.stringify(meta["anchor-position"])
+  end
+end
+
+local function add_anchor(heading)
+  if heading.identifier == "" then
+    return nil
+  end
+
+  local attr = pandoc.Attr("", { "anchor-link", position }, {
+    ["data-anchor-symbol"] = symbol,
+    ["aria-label"] = "Link to this section",
+  })
+  local link = pandoc.Link({}, "#" .. heading.identifier, "Link to this section", attr)
+  if position == "before" then
+    heading.content:insert(1, link)
+  else
+    heading.content:insert(link)
+  end
+  return heading
+end
+
+-- metadata has to be read in a separate pass, as pandoc would otherwise
+-- traverse the headings before the metadata
+return {
+  { Meta = read_meta },
+  { Header = add_anchor },
+}
@@ -2,7 +2,9 @@
   lib,
   # build dependencies
   runCommandLocal,
+  writeText,
   pandoc,
+  html-tidy,
   nixosOptionsDoc,
   ndg-stylesheet,
   # options
@@ -24,42 +26,169 @@
   specialArgs ? {},
   evaluatedModules ?
     lib.evalModules {
-      modules = rawModules;
+      # only option declarations are documented, so don't require definitions
+      # to be valid (or to refer to declared options)
+      modules = rawModules ++ [{_module.check = false;}];
       inherit specialArgs;
-      _modules.check = false;
     },
   title ? "My Option Documentation",
   templatePath ? ./assets/default-template.html,
   styleSheetPath ? ./assets/default-styles.scss,
   codeThemePath ? ./assets/default-syntax.theme,
   optionsDocArgs ? {},
+  extraCommonmarkExtensions ? [],
+  codeCopyButton ? false,
+  codeLineNumbers ? false,
+  headingAnchors ? true,
+  headingAnchorSymbol ? "#",
+  headingAnchorPosition ? "after",
+  headingIdStyle ? "pandoc",
+  numberSections ? false,
+  headingLevelOffset ? 0,
+  description ? null,
+  canonicalUrl ? null,
+  extraHeadHtml ? null,
+  bodyStartHtml ? null,
+  bodyEndHtml ? null,
+  analytics ? null,
+  includeOptions ? [],
+  excludeOptions ? [],
+  declarationLinks ? [],
+  optionFilter ? true,
+  externalLinkIcons ? false,
+  externalLinksNewTab ? false,
+  preBuild ? "",
+  postBuild ? "",
+  validateHtml ? false,
 } @ args:
 assert args ? specialArgs -> args ? rawModules;
-assert args ? evaluatedModules -> !(args ? rawModules); let
+assert args ? evaluatedModules -> !(args ? rawModules);
+assert lib.assertOneOf "headingIdStyle" headingIdStyle ["pandoc" "github" "ascii"];
+assert lib.assertOneOf "headingAnchorPosition" headingAnchorPosition ["before" "after"]; let
   inherit (lib.strings) optionalString;
 
-  configMD =
-    (nixosOptionsDoc (
-      (removeAttrs optionsDocArgs ["options"])
-      // {inherit (evaluatedModules) options;}
-    ))
-    .optionsCommonMark;
-in
-  runCommandLocal "generate-option-docs.html" {nativeBuildInputs = [pandoc];} (
-    ''
-      # convert to pandoc markdown instead of using commonmark directly,
-      # as the former automatically generates heading ids and TOC links.
-      pandoc \
-        --from commonmark \
-        --to markdown \
-        ${configMD} |
+  # pandoc markdown extensions selecting how heading ids are generated
+  headingIdExtensions = {
+    pandoc = "";
+    github = "+gfm_auto_identifiers";
+    ascii = "+ascii_identifiers";
+  };
+
+  # commonmark extensions to enable when reading the nixosOptionsDoc output
+  commonmarkExtensions =
+    [
+      "pipe_tables"
+      "strikeout"
+    ]
+    ++ extraCommonmarkExtensions;
+
+  # html fragments can be given either as a file or as a string of html
+  toHtmlFile = name: fragment:
+    if lib.isString fragment
+    then writeText name fragment
+    else fragment;
+
+  # whether an option matches `pattern` or is one of its sub-options. `*`
+  # matches within a single path component, e.g. `services.*.enable`.
+  matchesPattern = opt: pattern: let
+    regex = builtins.replaceStrings ["\\*"] ["[^.]*"] (lib.escapeRegex pattern);
+  in
+    builtins.match "${regex}(\\..*)?" opt.name != null;
+
+  isOptionIncluded = opt:
+    (includeOptions == [] || lib.any (matchesPattern opt) includeOptions)
+    && !(lib.any (matchesPattern opt) excludeOptions);
+
+  # turn a declaration under one of the `declarationLinks` paths into a
+  # link to that file at the corresponding url.
+  linkDeclaration = decl: let
+    isUnder = link: decl == toString link.path || lib.hasPrefix "${toString link.path}/" decl;
+    link = lib.findFirst isUnder null declarationLinks;
+    file = lib.removePrefix "/" (lib.removePrefix (toString link.path) decl);
+  in
+    if !(lib.isString decl) || link == null
+    then decl
+    else {
+      name = file;
+      url = "${lib.removeSuffix "/" link.url}/${file}";
+    };
+
+  optionsDoc = nixosOptionsDoc (
+    (removeAttrs optionsDocArgs ["options" "transformOptions"])
+    // {
+      inherit (evaluatedModules) options;
+
+      # link declarations and hide filtered options the same way nixosOptionsDoc
+      # hides invisible ones, after applying any user-provided transformation.
+      transformOptions = opt: let
+        transformed = (optionsDocArgs.transformOptions or lib.id) opt;
+      in
+        transformed
+        // {declarations = map linkDeclaration transformed.declarations;}
+        // lib.optionalAttrs (!isOptionIncluded transformed) {visible = false;};
+    }
+  );
+
+  configMD = optionsDoc.optionsCommonMark;
+
+  # options are level 2 headings without a level 1 heading above them, which
+  # pandoc would number 0.1, 0.2, ... so promote them when numbering sections,
+  # unless an offset was chosen explicitly
+  levelOffset =
+    if numberSections && headingLevelOffset == 0
+    then -1
+    else headingLevelOffset;
+
+  # heading options shared by the html and epub outputs. a negative offset
+  # promotes headings, a positive one demotes them.
+  headingArgs =
+    optionalString (levelOffset != 0) ''--shift-heading-level-by=${toString levelOffset} \''
+    + optionalString numberSections ''--number-sections \'';
 
+  # convert to pandoc markdown instead of using commonmark directly,
+  # as the former automatically generates heading ids and TOC links.
+  toPandocMarkdown = ''
+    pandoc \
+      --from commonmark${lib.concatMapStrings (ext: "+${ext}") commonmarkExtensions} \
+      --to markdown \
+      ${configMD} |
+  '';
+
+  epub = runCommandLocal "generate-option-docs.epub" {nativeBuildInputs = [pandoc];} (
+    toPandocMarkdown
+    + ''
+
+      # e-readers apply their own styling, so our stylesheet and template are
+      # left out here.
+      pandoc \
+       --sandbox \
+       --from markdown${headingIdExtensions.${headingIdStyle}} \
+       --to epub3 \
+       --metadata title="${title}" \
+       --toc \
+    ''
+    + optionalString (codeThemePath != null) ''--highlight-style ${codeThemePath} \''
+    + headingArgs
+    + "-o $out"
+  );
+in
+  runCommandLocal "generate-option-docs.html" {
+    nativeBuildInputs = [pandoc] ++ lib.optional validateHtml html-tidy;
+    passthru = {
+      inherit epub;
+      inherit (optionsDoc) optionsJSON;
+    };
+  } (
+    preBuild
+    + "\n"
+    + toPandocMarkdown
+    + ''
 
       # convert pandoc markdown to html using our own template and css files
       # where available. --sandbox is passed for extra security.
       pandoc \
        --sandbox \
-       --from markdown \
+       --from markdown${headingIdExtensions.${headingIdStyle}} \
        --to html \
        --metadata title="${title}" \
        --toc \
@@ -68,5 +197,30 @@ in
     + optionalString (templatePath != null) ''--template ${templatePath} \''
     + optionalString (styleSheetPath != null) ''--css ${ndg-stylesheet.override {inherit styleSheetPath;}} \''
     + optionalString (codeThemePath != null) ''--highlight-style ${codeThemePath} \''
-    + "-o $out"
+    + headingArgs
+    + optionalString codeCopyButton ''--metadata copy-button \''
+    + optionalString codeLineNumbers ''--lua-filter ${./assets/code-line-numbers.lua} \''
+    + optionalString headingAnchors ''--lua-filter ${./assets/heading-anchors.lua} \''
+    + optionalString headingAnchors ''--metadata anchor-links \''
+    + optionalString headingAnchors ''--metadata anchor-symbol=${lib.escapeShellArg headingAnchorSymbol} \''
+    + optionalString headingAnchors ''--metadata anchor-position=${headingAnchorPosition} \''
+    + optionalString optionFilter ''--metadata option-filter \''
+    + optionalString (externalLinkIcons || externalLinksNewTab) ''--lua-filter ${./assets/external-links.lua} \''
+    + optionalString externalLinkIcons ''--metadata external-links-icon \''
+    + optionalString externalLinksNewTab ''--metadata external-links-new-tab \''
+    + optionalString (description != null) ''--metadata description=${lib.escapeShellArg description} \''
+    + optionalString (canonicalUrl != null) ''--metadata canonical-url=${lib.escapeShellArg canonicalUrl} \''
+    + optionalString (extraHeadHtml != null) ''--include-in-header ${toHtmlFile "head.html" extraHeadHtml} \''
+    + optionalString (analytics != null) ''--include-in-header ${writeText "analytics.html" (import ./analytics.nix {inherit lib;} analytics)} \''
+    + optionalString (bodyStartHtml != null) ''--include-before-body ${toHtmlFile "body-start.html" bodyStartHtml} \''
+    + optionalString (bodyEndHtml != null) ''--include-after-body ${toHtmlFile "body-end.html" bodyEndHtml} \''
+    + "-o $out\n"
+    + optionalString validateHtml ''
+
+      # report structural problems such as duplicate ids or unclosed tags
+      # coming from raw html in option descriptions. tidy exits with 1 if it
+      # only found warnings, which should not fail the build.
+      tidy -quiet -errors $out || [ $? -eq 1 ]
+    ''
+    + postBuild
   )
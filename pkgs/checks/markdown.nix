@@ -0,0 +1,30 @@
+# options whose descriptions use the markdown syntax ndg's checks expect to
+# make it through nixos-render-docs and pandoc
+{lib, ...}: {
+  options.fixtures = {
+    table = lib.mkOption {
+      type = lib.types.str;
+      default = "";
+      description = ''
+        A description with a pipe table.
+
+        | Value | Meaning    |
+        | ----- | ---------- |
+        | `a`   | first one  |
+        | `b`   | second one |
+      '';
+    };
+
+    inline = lib.mkOption {
+      type = lib.types.str;
+      default = "";
+      description = "A ~~removed~~ word, a ==highlighted== one, H~2~O and x^2^.";
+    };
+
+    link = lib.mkOption {
+      type = lib.types.str;
+      default = "";
+      description = "A description linking to [NixOS](https://nixos.org).";
+    };
+  };
+}
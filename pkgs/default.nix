@@ -13,6 +13,26 @@
       ndg-builder = final.callPackage ./builder.nix {};
       ndg-stylesheet = final.callPackage ./stylesheet.nix {};
     };
+
+    # fails unless the generated page contains each of the given strings
+    assertContains = name: page: strings:
+      pkgs.runCommandLocal "check-${name}" {} (
+        lib.concatMapStrings (string: ''
+          grep -qF ${lib.escapeShellArg string} ${page} || {
+            echo ${lib.escapeShellArg "${name}: ${string} not found in the generated page"}
+            exit 1
+          }
+        '')
+        strings
+        + "touch $out\n"
+      );
+
+    # documentation of options whose descriptions use markdown extensions,
+    # with the opt-in extensions suggested in the readme enabled
+    markdownDocs = self'.packages.ndg-builder.override {
+      rawModules = [./checks/markdown.nix];
+      extraCommonmarkExtensions = ["mark" "subscript" "superscript"];
+    };
   in {
     checks = {
       nixos = self'.packages.ndg-builder.override {
@@ -29,6 +49,61 @@
           ];
         };
       };
+
+      epub = self'.packages.ndg-builder.epub;
+
+      # exercises option filtering, declaration links and html validation on
+      # the module shipped with the flake template
+      template = self'.packages.ndg-builder.override {
+        rawModules = [../templates/default/module.nix];
+        includeOptions = ["programs.*"];
+        excludeOptions = ["programs.hello.enable"];
+        declarationLinks = [
+          {
+            path = ../templates/default;
+            url = "https://github.com/feel-co/ndg/blob/main/templates/default";
+          }
+        ];
+        validateHtml = true;
+      };
+
+      # option descriptions are re-rendered by nixos-render-docs before pandoc
+      # reads them, so check the syntax ndg enables actually survives that
+      tables = assertContains "tables" markdownDocs ["<table"];
+      inline = assertContains "inline" markdownDocs ["<del>" "<mark>" "<sub>" "<sup>"];
+
+      # builds with the optional page features enabled, so that each of the
+      # builder's arguments is evaluated and run at least once
+      features = assertContains "features" (self'.packages.ndg-builder.override {
+        rawModules = [../templates/default/module.nix ./checks/markdown.nix];
+        codeCopyButton = true;
+        codeLineNumbers = true;
+        headingAnchorSymbol = "¶";
+        headingAnchorPosition = "before";
+        headingIdStyle = "github";
+        numberSections = true;
+        headingLevelOffset = 1;
+        description = "Options of the ndg checks";
+        canonicalUrl = "https://example.org/options.html";
+        extraHeadHtml = ''<meta name="ndg-head" content="">'';
+        bodyStartHtml = pkgs.writeText "body-start.html" ''<div id="ndg-body-start"></div>'';
+        bodyEndHtml = ''<div id="ndg-body-end"></div>'';
+        analytics = {
+          provider = "plausible";
+          domain = "example.org";
+        };
+        externalLinkIcons = true;
+        externalLinksNewTab = true;
+      }) [
+        "ndg-head"
+        "ndg-body-start"
+        "ndg-body-end"
+        "plausible.io"
+        "https://example.org/options.html"
+        "header-section-number"
+        ''data-anchor-symbol="¶"''
+        ''target="_blank"''
+      ];
     };
 
     devShells.default = final.mkShell {
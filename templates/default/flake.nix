@@ -0,0 +1,30 @@
+{
+  description = "Option documentation built with ndg";
+
+  inputs = {
+    nixpkgs.url = "github:NixOS/nixpkgs/nixos-unstable";
+    ndg = {
+      url = "github:feel-co/ndg";
+      inputs.nixpkgs.follows = "nixpkgs";
+    };
+  };
+
+  outputs = {
+    nixpkgs,
+    ndg,
+    ...
+  }: let
+    systems = ["x86_64-linux" "aarch64-linux" "aarch64-darwin" "x86_64-darwin"];
+    forAllSystems = nixpkgs.lib.genAttrs systems;
+  in {
+    nixosModules.default = ./module.nix;
+
+    # build with `nix build .#docs`
+    packages = forAllSystems (system: {
+      docs = ndg.packages.${system}.ndg-builder.override {
+        title = "My Module Documentation";
+        rawModules = [./module.nix];
+      };
+    });
+  };
+}
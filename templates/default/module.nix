@@ -0,0 +1,11 @@
+{lib, ...}: {
+  options.programs.hello = {
+    enable = lib.mkEnableOption "hello";
+
+    greeting = lib.mkOption {
+      default = "Hello, world!";
+      description = "The greeting to print.";
+      type = lib.types.str;
+    };
+  };
+}